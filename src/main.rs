@@ -1,10 +1,20 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
 
 use anyhow::Result;
 use chrono::prelude::*;
 use clap::Parser;
+use handlebars::{handlebars_helper, Handlebars};
 use num_format::ToFormattedString;
 use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+
+mod progress;
+
+use progress::{DeckSnapshot, ProgressDb, ProgressPoint};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum Queue {
@@ -38,10 +48,21 @@ impl TryFrom<u8> for Queue {
 
 #[derive(Debug, PartialEq, Eq)]
 struct Card {
+    id: i64,
+    did: i64,
     fields: Vec<String>,
     queue: Queue,
     reps: u32,
     lapses: u32,
+    due: i64,
+    ivl: i64,
+    factor: u32,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Deck {
+    id: i64,
+    name: String,
 }
 
 #[derive(Parser, Debug)]
@@ -51,7 +72,8 @@ struct Args {
     #[clap(short, long)]
     collection_path: PathBuf,
 
-    /// Anki deck ID to generate statistics for
+    /// Comma-separated list of deck IDs and/or name globs (e.g. "1,Core2300::*")
+    /// to generate statistics for
     #[clap(short, long)]
     deck_id: String,
 
@@ -62,65 +84,752 @@ struct Args {
     /// Path to the template HTML file to use
     #[clap(short)]
     template: Option<PathBuf>,
+
+    /// Number of days ahead to include in the due forecast
+    #[clap(long, default_value_t = 30)]
+    forecast_days: usize,
+
+    /// Path to a TOML config describing the note field layout and dictionary
+    /// link template
+    #[clap(long)]
+    config: Option<PathBuf>,
+
+    /// Index of the headword field in each note (overrides config)
+    #[clap(long)]
+    front_field: Option<usize>,
+
+    /// Index of the reading field in each note, if any (overrides config)
+    #[clap(long)]
+    reading_field: Option<usize>,
+
+    /// Index of the gloss/translation field in each note, if any (overrides
+    /// config)
+    #[clap(long)]
+    english_field: Option<usize>,
+
+    /// Dictionary link URL template; supports {front}, {reading} and
+    /// {english} placeholders (overrides config)
+    #[clap(long)]
+    link_template: Option<String>,
+
+    /// Path to the local SQLite database used to store historical
+    /// per-deck snapshots, created and migrated on first run
+    #[clap(long, default_value = "./progress.sqlite3")]
+    progress_db: PathBuf,
+
+    /// Keep running, regenerating the report whenever the collection
+    /// file changes
+    #[clap(long)]
+    watch: bool,
+
+    /// How long to wait after the last detected change before rebuilding,
+    /// so a burst of Anki writes coalesces into one regeneration
+    #[clap(long, default_value_t = 2_000)]
+    watch_debounce_ms: u64,
+}
+
+/// Describes which note fields hold the headword, reading and gloss, and
+/// how to build a dictionary link for a card. Defaults match the original
+/// Japanese Core note type; non-Japanese decks (e.g. a hieroglyph deck
+/// with a transliteration and translation field) can override any of
+/// these via `--config` or the individual `--*-field`/`--link-template`
+/// flags.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct FieldConfig {
+    front_field: usize,
+    reading_field: Option<usize>,
+    english_field: Option<usize>,
+    link_template: String,
+}
+
+impl Default for FieldConfig {
+    fn default() -> Self {
+        FieldConfig {
+            front_field: 0,
+            reading_field: Some(1),
+            english_field: Some(2),
+            link_template: "https://jisho.org/search/{front}".to_owned(),
+        }
+    }
+}
+
+impl FieldConfig {
+    /// Loads the base config from `--config`, if given, then applies any
+    /// `--*-field`/`--link-template` CLI overrides on top.
+    fn load(args: &Args) -> Result<Self> {
+        let mut config = match &args.config {
+            Some(path) => toml::from_str(&std::fs::read_to_string(path)?)?,
+            None => FieldConfig::default(),
+        };
+
+        if let Some(front_field) = args.front_field {
+            config.front_field = front_field;
+        }
+        if let Some(reading_field) = args.reading_field {
+            config.reading_field = Some(reading_field);
+        }
+        if let Some(english_field) = args.english_field {
+            config.english_field = Some(english_field);
+        }
+        if let Some(link_template) = &args.link_template {
+            config.link_template = link_template.clone();
+        }
+
+        Ok(config)
+    }
+}
+
+/// Looks up a note field by index, falling back to an empty string when
+/// the deck's note type has fewer fields than the config references.
+fn field_value(fields: &[String], index: Option<usize>) -> String {
+    index
+        .and_then(|i| fields.get(i))
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn render_link(template: &str, front: &str, reading: &str, english: &str) -> String {
+    template
+        .replace("{front}", front)
+        .replace("{reading}", reading)
+        .replace("{english}", english)
+}
+
+/// Forecast of upcoming review-queue cards, bucketed by the day they fall
+/// due relative to today. `overdue` counts review cards already past due;
+/// `buckets[i]` counts cards due `i` days from now.
+#[derive(Debug, Default)]
+struct Forecast {
+    overdue: usize,
+    buckets: Vec<usize>,
+}
+
+impl Forecast {
+    fn new(n_days: usize) -> Self {
+        Forecast {
+            overdue: 0,
+            buckets: vec![0; n_days],
+        }
+    }
+
+    /// Records a review-queue card given its `due` day number (counted in
+    /// days from the collection's creation day `crt`) and how many days
+    /// have elapsed since `crt`.
+    fn record(&mut self, due: i64, days_since_crt: i64) {
+        let days_until = due - days_since_crt;
+        if days_until < 0 {
+            self.overdue += 1;
+        } else if let Some(bucket) = self.buckets.get_mut(days_until as usize) {
+            *bucket += 1;
+        }
+    }
+
+    fn max_bucket(&self) -> usize {
+        self.buckets.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Buckets rendered as template-ready rows, each with a bar height
+    /// (as a percentage of the busiest day) for the template to draw.
+    fn bucket_contexts(&self) -> Vec<ForecastBucketContext> {
+        let max = self.max_bucket().max(1);
+        self.buckets
+            .iter()
+            .enumerate()
+            .map(|(day, &count)| ForecastBucketContext {
+                day,
+                count,
+                height_pct: count as f64 / max as f64 * 100.0,
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct ForecastBucketContext {
+    day: usize,
+    count: usize,
+    height_pct: f64,
+}
+
+#[cfg(test)]
+mod forecast_tests {
+    use super::*;
+
+    #[test]
+    fn record_buckets_a_card_due_today() {
+        let mut forecast = Forecast::new(30);
+        forecast.record(10, 10);
+        assert_eq!(forecast.overdue, 0);
+        assert_eq!(forecast.buckets[0], 1);
+    }
+
+    #[test]
+    fn record_counts_a_card_due_before_now_as_overdue() {
+        let mut forecast = Forecast::new(30);
+        forecast.record(5, 10);
+        assert_eq!(forecast.overdue, 1);
+        assert!(forecast.buckets.iter().all(|&count| count == 0));
+    }
+
+    #[test]
+    fn record_drops_a_card_due_past_the_forecast_window() {
+        let mut forecast = Forecast::new(30);
+        forecast.record(10 + 30, 10);
+        assert_eq!(forecast.overdue, 0);
+        assert!(forecast.buckets.iter().all(|&count| count == 0));
+    }
+
+    #[test]
+    fn bucket_contexts_scales_heights_to_the_busiest_day() {
+        let mut forecast = Forecast::new(3);
+        forecast.record(10, 10);
+        forecast.record(10, 10);
+        forecast.record(12, 10);
+
+        let contexts = forecast.bucket_contexts();
+        assert_eq!(
+            contexts,
+            vec![
+                ForecastBucketContext { day: 0, count: 2, height_pct: 100.0 },
+                ForecastBucketContext { day: 1, count: 0, height_pct: 0.0 },
+                ForecastBucketContext { day: 2, count: 1, height_pct: 50.0 },
+            ]
+        );
+    }
+}
+
+#[derive(Serialize, Clone)]
+struct CardContext {
+    front: String,
+    reading: String,
+    english: String,
+    link: String,
+    queue_class: &'static str,
+    reps: u32,
+    lapses: u32,
+    due: i64,
+    ivl: i64,
+    ease: u32,
+}
+
+/// A card's last-rendered context alongside the fields that determine
+/// whether it needs to be rendered again. When a rebuild sees the same
+/// `(reps, lapses, queue, due)` for a card id, the cached context is
+/// reused instead of recomputing field lookups and the dictionary link.
+struct CachedCard {
+    reps: u32,
+    lapses: u32,
+    queue: Queue,
+    due: i64,
+    context: CardContext,
+}
+
+/// Whether a cached card's context is still fresh, i.e. none of the
+/// fields it was rendered from have changed since it was cached.
+fn cached_card_is_fresh(cached: &CachedCard, card: &Card) -> bool {
+    cached.reps == card.reps
+        && cached.lapses == card.lapses
+        && cached.queue == card.queue
+        && cached.due == card.due
+}
+
+#[cfg(test)]
+mod cached_card_tests {
+    use super::*;
+
+    fn card(reps: u32, lapses: u32, queue: Queue, due: i64) -> Card {
+        Card {
+            id: 1,
+            did: 1,
+            fields: vec!["front".to_owned(), "reading".to_owned(), "english".to_owned()],
+            queue,
+            reps,
+            lapses,
+            due,
+            ivl: 1,
+            factor: 2500,
+        }
+    }
+
+    fn cached_from(card: &Card) -> CachedCard {
+        CachedCard {
+            reps: card.reps,
+            lapses: card.lapses,
+            queue: card.queue,
+            due: card.due,
+            context: CardContext {
+                front: String::new(),
+                reading: String::new(),
+                english: String::new(),
+                link: String::new(),
+                queue_class: card.queue.class(),
+                reps: card.reps,
+                lapses: card.lapses,
+                due: card.due,
+                ivl: card.ivl,
+                ease: card.factor / 10,
+            },
+        }
+    }
+
+    #[test]
+    fn fresh_when_nothing_changed() {
+        let card = card(3, 0, Queue::Review, 100);
+        let cached = cached_from(&card);
+        assert!(cached_card_is_fresh(&cached, &card));
+    }
+
+    #[test]
+    fn stale_when_reps_changed() {
+        let card = card(3, 0, Queue::Review, 100);
+        let cached = cached_from(&card);
+        let mut changed = card;
+        changed.reps += 1;
+        assert!(!cached_card_is_fresh(&cached, &changed));
+    }
+
+    #[test]
+    fn stale_when_lapses_changed() {
+        let card = card(3, 0, Queue::Review, 100);
+        let cached = cached_from(&card);
+        let mut changed = card;
+        changed.lapses += 1;
+        assert!(!cached_card_is_fresh(&cached, &changed));
+    }
+
+    #[test]
+    fn stale_when_queue_changed() {
+        let card = card(3, 0, Queue::Learning, 100);
+        let cached = cached_from(&card);
+        let mut changed = card;
+        changed.queue = Queue::Review;
+        assert!(!cached_card_is_fresh(&cached, &changed));
+    }
+
+    #[test]
+    fn stale_when_due_changed() {
+        let card = card(3, 0, Queue::Review, 100);
+        let cached = cached_from(&card);
+        let mut changed = card;
+        changed.due += 1;
+        assert!(!cached_card_is_fresh(&cached, &changed));
+    }
+}
+
+/// Running tally of a deck's cards seen so far in this run, by queue.
+#[derive(Debug, Default, Clone, Copy)]
+struct DeckCounts {
+    n_cards: usize,
+    n_learned: usize,
+    n_new: usize,
+    n_learning: usize,
+    n_review: usize,
+}
+
+#[derive(Serialize)]
+struct ProgressPointContext {
+    recorded_at: String,
+    n_cards: usize,
+    n_learned: usize,
+}
+
+/// Renders the learned-over-time series as an inline SVG line chart.
+fn render_progress_svg(points: &[ProgressPoint]) -> String {
+    if points.len() < 2 {
+        return String::new();
+    }
+
+    const WIDTH: f64 = 600.0;
+    const HEIGHT: f64 = 200.0;
+
+    let max_learned = points.iter().map(|p| p.n_learned).max().unwrap_or(0).max(1) as f64;
+    let step = WIDTH / (points.len() - 1) as f64;
+    let coords = points
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let x = i as f64 * step;
+            let y = HEIGHT - (p.n_learned as f64 / max_learned * HEIGHT);
+            format!("{:.1},{:.1}", x, y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    format!(
+        "<svg viewBox='0 0 {width} {height}' class='progress-chart' \
+            xmlns='http://www.w3.org/2000/svg'>\
+            <polyline points='{coords}' fill='none' stroke='currentColor' stroke-width='2'/>\
+        </svg>",
+        width = WIDTH,
+        height = HEIGHT,
+        coords = coords,
+    )
+}
+
+#[derive(Serialize)]
+struct DeckContext {
+    id: i64,
+    name: String,
+    n_cards: usize,
+    n_learned: usize,
+    learned_percentage: f64,
 }
 
+#[derive(Serialize)]
+struct TemplateContext {
+    cards: Vec<CardContext>,
+    decks: Vec<DeckContext>,
+    n_cards: usize,
+    n_learned: usize,
+    learned_percentage: f64,
+    forecast_overdue: usize,
+    forecast_buckets: Vec<ForecastBucketContext>,
+    progress: String,
+    progress_series: Vec<ProgressPointContext>,
+    now: String,
+}
+
+handlebars_helper!(thousands: |v: i64| v.to_formatted_string(&num_format::Locale::en));
+
+/// The escape character used in `LIKE ... ESCAPE` patterns built from a
+/// user-supplied glob.
+const LIKE_ESCAPE: char = '\\';
+
+/// Translates a `*`-glob into a SQL `LIKE` pattern, escaping any `%`, `_`
+/// or `\` that were already present in the glob so they're matched
+/// literally instead of being interpreted as SQL wildcards.
+fn glob_to_like_pattern(glob: &str) -> String {
+    let mut like_pattern = String::with_capacity(glob.len());
+    for ch in glob.chars() {
+        match ch {
+            '%' | '_' | LIKE_ESCAPE => {
+                like_pattern.push(LIKE_ESCAPE);
+                like_pattern.push(ch);
+            }
+            '*' => like_pattern.push('%'),
+            _ => like_pattern.push(ch),
+        }
+    }
+    like_pattern
+}
+
+/// Resolves a comma-separated list of deck IDs and/or name globs (e.g.
+/// `"1,Core2300::*"`) against the `decks` table, returning every matching
+/// deck. A glob is any pattern containing `*`, translated to a SQL `LIKE`
+/// pattern; anything else is matched as a literal deck ID.
+fn resolve_decks(conn: &Connection, deck_id_arg: &str) -> Result<Vec<Deck>> {
+    let patterns = deck_id_arg
+        .split(',')
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .collect::<Vec<_>>();
+    if patterns.is_empty() {
+        anyhow::bail!("--deck-id must name at least one deck id or glob");
+    }
+
+    let mut decks = Vec::new();
+    let mut seen_ids = std::collections::HashSet::new();
+
+    for pattern in patterns {
+        let matched = if pattern.contains('*') {
+            let like_pattern = glob_to_like_pattern(pattern);
+            let mut stmt =
+                conn.prepare("SELECT id, name FROM decks WHERE name LIKE ?1 ESCAPE '\\'")?;
+            let rows = stmt.query_map([&like_pattern], |row| {
+                Ok(Deck {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                })
+            })?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        } else {
+            let did: i64 = pattern
+                .parse()
+                .map_err(|_| anyhow::anyhow!("'{}' is not a valid deck ID or glob", pattern))?;
+            let mut stmt = conn.prepare("SELECT id, name FROM decks WHERE id = ?1")?;
+            let rows = stmt.query_map([did], |row| {
+                Ok(Deck {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                })
+            })?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+
+        if matched.is_empty() {
+            anyhow::bail!("no deck matched '{}'", pattern);
+        }
+
+        for deck in matched {
+            if seen_ids.insert(deck.id) {
+                decks.push(deck);
+            }
+        }
+    }
+
+    Ok(decks)
+}
+
+#[cfg(test)]
+mod resolve_decks_tests {
+    use super::*;
+
+    #[test]
+    fn glob_to_like_pattern_turns_star_into_percent() {
+        assert_eq!(glob_to_like_pattern("Core2300::*"), "Core2300::%");
+    }
+
+    #[test]
+    fn glob_to_like_pattern_escapes_existing_like_wildcards() {
+        assert_eq!(glob_to_like_pattern("100%_done*"), "100\\%\\_done%");
+    }
+
+    #[test]
+    fn glob_to_like_pattern_escapes_existing_backslashes() {
+        assert_eq!(glob_to_like_pattern("a\\b*"), "a\\\\b%");
+    }
+
+    fn test_decks_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE decks (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+             INSERT INTO decks (id, name) VALUES
+                (1, 'Core2300::Lesson1'),
+                (2, 'Core2300::Lesson2'),
+                (3, 'Core2300_Backup'),
+                (4, 'Hieroglyphs');",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn resolve_decks_matches_a_literal_id() {
+        let conn = test_decks_db();
+        let decks = resolve_decks(&conn, "4").unwrap();
+        assert_eq!(decks.len(), 1);
+        assert_eq!(decks[0].name, "Hieroglyphs");
+    }
+
+    #[test]
+    fn resolve_decks_matches_only_decks_under_a_glob() {
+        let conn = test_decks_db();
+        let decks = resolve_decks(&conn, "Core2300::*").unwrap();
+        let mut names = decks.into_iter().map(|d| d.name).collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["Core2300::Lesson1", "Core2300::Lesson2"]);
+    }
+
+    #[test]
+    fn resolve_decks_rejects_an_empty_deck_id() {
+        let conn = test_decks_db();
+        assert!(resolve_decks(&conn, "   ").is_err());
+    }
+
+    #[test]
+    fn resolve_decks_rejects_a_pattern_with_no_match() {
+        let conn = test_decks_db();
+        assert!(resolve_decks(&conn, "999").is_err());
+    }
+
+    #[test]
+    fn resolve_decks_rejects_a_non_numeric_non_glob_pattern() {
+        let conn = test_decks_db();
+        assert!(resolve_decks(&conn, "NotAnIdOrGlob").is_err());
+    }
+}
+
+/// How often to poll the collection file's mtime while `--watch` is idle,
+/// waiting for a change to start debouncing.
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 fn main() -> Result<()> {
     let args = Args::parse();
+    let mut card_cache: HashMap<i64, CachedCard> = HashMap::new();
+
+    generate_report(&args, &mut card_cache)?;
+
+    if args.watch {
+        watch(&args, &mut card_cache)?;
+    }
+
+    Ok(())
+}
+
+/// Reads the collection file's mtime, logging and returning `None` on
+/// failure instead of propagating the error. A transient stat failure
+/// (e.g. the file briefly replaced mid atomic-save) should not bring
+/// down a long-running `--watch` process; the next poll just tries again.
+fn poll_mtime(collection_path: &std::path::Path) -> Option<SystemTime> {
+    match std::fs::metadata(collection_path).and_then(|metadata| metadata.modified()) {
+        Ok(mtime) => Some(mtime),
+        Err(err) => {
+            eprintln!(
+                "warning: failed to read mtime of {:?}, will retry: {}",
+                collection_path, err
+            );
+            None
+        }
+    }
+}
+
+/// Polls the collection file's mtime, debouncing a burst of writes into a
+/// single rebuild, and regenerates the report each time it settles on a
+/// new mtime. Runs until the process is killed.
+fn watch(args: &Args, card_cache: &mut HashMap<i64, CachedCard>) -> Result<()> {
+    let mut last_mtime = loop {
+        if let Some(mtime) = poll_mtime(&args.collection_path) {
+            break mtime;
+        }
+        std::thread::sleep(WATCH_POLL_INTERVAL);
+    };
+
+    loop {
+        std::thread::sleep(WATCH_POLL_INTERVAL);
+        let mtime = match poll_mtime(&args.collection_path) {
+            Some(mtime) => mtime,
+            None => continue,
+        };
+        if mtime == last_mtime {
+            continue;
+        }
+
+        let settled_mtime = debounce(&args.collection_path, mtime, args.watch_debounce_ms);
+        last_mtime = settled_mtime;
 
-    let conn = Connection::open_with_flags(args.collection_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        println!("collection changed, regenerating report");
+        if let Err(err) = generate_report(args, card_cache) {
+            eprintln!(
+                "warning: failed to regenerate report, will retry on the next change: {}",
+                err
+            );
+        }
+    }
+}
+
+/// Waits until the collection file's mtime stops changing for
+/// `debounce_ms`, re-checking once every `debounce_ms`, so a burst of Anki
+/// writes coalesces into a single rebuild. A transient stat failure while
+/// debouncing is treated as "still settling" rather than aborting the watch.
+fn debounce(collection_path: &std::path::Path, mut mtime: SystemTime, debounce_ms: u64) -> SystemTime {
+    loop {
+        std::thread::sleep(Duration::from_millis(debounce_ms));
+        let current_mtime = match poll_mtime(collection_path) {
+            Some(current_mtime) => current_mtime,
+            None => continue,
+        };
+        if current_mtime == mtime {
+            return mtime;
+        }
+        mtime = current_mtime;
+    }
+}
+
+fn generate_report(args: &Args, card_cache: &mut HashMap<i64, CachedCard>) -> Result<()> {
+    let conn =
+        Connection::open_with_flags(&args.collection_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+
+    let field_config = FieldConfig::load(args)?;
+
+    let decks = resolve_decks(&conn, &args.deck_id)?;
+
+    let crt: i64 = conn.query_row("SELECT crt FROM col", [], |row| row.get(0))?;
+    let days_since_crt = (Local::now().timestamp() - crt) / 86_400;
+
+    let placeholders = decks.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
     let mut cards_stmt = conn.prepare(&format!(
-        "SELECT notes.flds, cards.queue, cards.reps, cards.lapses \
+        "SELECT cards.id, cards.did, notes.flds, cards.queue, cards.reps, cards.lapses, \
+                cards.due, cards.ivl, cards.factor \
             FROM cards \
             INNER JOIN notes ON notes.id = cards.nid \
-            WHERE cards.did = {deck_id}
+            WHERE cards.did IN ({placeholders}) \
             ORDER BY notes.id",
-        deck_id = args.deck_id
+        placeholders = placeholders
     ))?;
 
-    let cards_iter = cards_stmt.query_map([], |row| {
-        Ok(Card {
-            fields: row
-                .get::<_, String>(0)?
-                .split('\x1f')
-                .map(|string| string.to_owned())
-                .collect::<Vec<String>>(),
-            queue: row
-                .get::<_, u8>(1)?
-                .try_into()
-                .expect("cannot convert unexpected queue value"),
-            reps: row.get(2)?,
-            lapses: row.get(3)?,
-        })
-    })?;
+    let cards_iter = cards_stmt.query_map(
+        rusqlite::params_from_iter(decks.iter().map(|d| d.id)),
+        |row| {
+            Ok(Card {
+                id: row.get(0)?,
+                did: row.get(1)?,
+                fields: row
+                    .get::<_, String>(2)?
+                    .split('\x1f')
+                    .map(|string| string.to_owned())
+                    .collect::<Vec<String>>(),
+                queue: row
+                    .get::<_, u8>(3)?
+                    .try_into()
+                    .expect("cannot convert unexpected queue value"),
+                reps: row.get(4)?,
+                lapses: row.get(5)?,
+                due: row.get(6)?,
+                ivl: row.get(7)?,
+                factor: row.get(8)?,
+            })
+        },
+    )?;
 
     let mut n_cards: usize = 0;
     let mut n_learned: usize = 0;
-    let mut cards = String::new();
+    let mut cards = Vec::new();
+    let mut per_deck: HashMap<i64, DeckCounts> = HashMap::new();
+    let mut forecast = Forecast::new(args.forecast_days);
 
     for card in cards_iter {
         let card = card?;
-        if card.queue == Queue::Review {
-            n_learned += 1;
+        let subtotal = per_deck.entry(card.did).or_default();
+        subtotal.n_cards += 1;
+        match card.queue {
+            Queue::New => subtotal.n_new += 1,
+            Queue::Learning => subtotal.n_learning += 1,
+            Queue::Review => {
+                subtotal.n_review += 1;
+                subtotal.n_learned += 1;
+                n_learned += 1;
+                forecast.record(card.due, days_since_crt);
+            }
         }
         n_cards += 1;
-        // println!("{:?}", card);
-        cards.push_str(&format!(
-            "<a href='https://jisho.org/search/{front}' class='card-link'>\
-                <div class='card card-{queue_class}'>{front}\
-                    <div class='card-hover'>\
-                        <div class='card-meaning'>{reading}; {english}</div>
-                        reviews: {reps}<br/>
-                        lapses: {lapses}<br/>
-                    </div>\
-                </div>\
-            </a>\n",
-            queue_class = card.queue.class(),
-            front = card.fields[0],
-            reps = card.reps,
-            lapses = card.lapses,
-            reading = card.fields[1],
-            english = card.fields[2],
-        ));
+
+        let cached = card_cache
+            .get(&card.id)
+            .filter(|cached| cached_card_is_fresh(cached, &card));
+        let card_context = match cached {
+            Some(cached) => cached.context.clone(),
+            None => {
+                let front = field_value(&card.fields, Some(field_config.front_field));
+                let reading = field_value(&card.fields, field_config.reading_field);
+                let english = field_value(&card.fields, field_config.english_field);
+                let link = render_link(&field_config.link_template, &front, &reading, &english);
+                let context = CardContext {
+                    front,
+                    reading,
+                    english,
+                    link,
+                    queue_class: card.queue.class(),
+                    reps: card.reps,
+                    lapses: card.lapses,
+                    due: card.due,
+                    ivl: card.ivl,
+                    ease: card.factor / 10,
+                };
+                card_cache.insert(
+                    card.id,
+                    CachedCard {
+                        reps: card.reps,
+                        lapses: card.lapses,
+                        queue: card.queue,
+                        due: card.due,
+                        context: context.clone(),
+                    },
+                );
+                context
+            }
+        };
+        cards.push(card_context);
     }
 
     let learned_percentage: f64 = n_learned as f64 / n_cards as f64 * 100.0;
@@ -128,32 +837,86 @@ fn main() -> Result<()> {
         "learned {}/{} ({:.2}%)",
         n_learned, n_cards, learned_percentage
     );
+    let mut decks_context = Vec::with_capacity(decks.len());
+    let mut snapshots = Vec::with_capacity(decks.len());
+    for deck in decks {
+        let counts = per_deck.get(&deck.id).copied().unwrap_or_default();
+        let deck_percentage = if counts.n_cards > 0 {
+            counts.n_learned as f64 / counts.n_cards as f64 * 100.0
+        } else {
+            0.0
+        };
+        println!(
+            "  {} ({}): learned {}/{} ({:.2}%)",
+            deck.name, deck.id, counts.n_learned, counts.n_cards, deck_percentage
+        );
+        snapshots.push(DeckSnapshot {
+            deck_id: deck.id,
+            deck_name: deck.name.clone(),
+            n_cards: counts.n_cards,
+            n_learned: counts.n_learned,
+            n_new: counts.n_new,
+            n_learning: counts.n_learning,
+            n_review: counts.n_review,
+        });
+        decks_context.push(DeckContext {
+            id: deck.id,
+            name: deck.name,
+            n_cards: counts.n_cards,
+            n_learned: counts.n_learned,
+            learned_percentage: deck_percentage,
+        });
+    }
 
-    let mut tokens = HashMap::new();
-    tokens.insert(
-        "n_learned",
-        n_learned.to_formatted_string(&num_format::Locale::en),
-    );
-    tokens.insert(
-        "n_cards",
-        n_cards.to_formatted_string(&num_format::Locale::en),
-    );
-    tokens.insert(
-        "learned_percentage_pretty",
-        format!("{:.2}", learned_percentage),
+    let run_recorded_at = Local::now().to_rfc3339();
+    let mut progress_db = ProgressDb::open(&args.progress_db)?;
+    progress_db.record_run(&run_recorded_at, &snapshots)?;
+    let progress_series = progress_db.fetch_progress(&snapshots.iter().map(|s| s.deck_id).collect::<Vec<_>>())?;
+
+    println!(
+        "forecast: {} overdue, {} due today, {} due over the next {} days",
+        forecast.overdue,
+        forecast.buckets.first().copied().unwrap_or(0),
+        forecast.buckets.iter().sum::<usize>(),
+        args.forecast_days
     );
-    tokens.insert("cards", cards);
-    tokens.insert("now", Local::now().to_rfc3339());
 
-    let template_path = args.template.unwrap_or("./template.html".parse().unwrap());
-    let mut template = std::fs::read_to_string(template_path)?;
-    for (token, value) in tokens.iter() {
-        template = template.replace(&format!("{{{}}}", token), value);
-    }
+    let context = TemplateContext {
+        cards,
+        decks: decks_context,
+        n_cards,
+        n_learned,
+        learned_percentage,
+        forecast_overdue: forecast.overdue,
+        forecast_buckets: forecast.bucket_contexts(),
+        progress: render_progress_svg(&progress_series),
+        progress_series: progress_series
+            .into_iter()
+            .map(|p| ProgressPointContext {
+                recorded_at: p.recorded_at,
+                n_cards: p.n_cards,
+                n_learned: p.n_learned,
+            })
+            .collect(),
+        now: run_recorded_at,
+    };
+
+    let mut handlebars = Handlebars::new();
+    handlebars.register_helper("thousands", Box::new(thousands));
+
+    let template_path = args
+        .template
+        .clone()
+        .unwrap_or_else(|| "./template.html".parse().unwrap());
+    let template = std::fs::read_to_string(template_path)?;
+    let rendered = handlebars.render_template(&template, &context)?;
 
-    let output_path = args.output.unwrap_or("./core2300.html".parse().unwrap());
+    let output_path = args
+        .output
+        .clone()
+        .unwrap_or_else(|| "./core2300.html".parse().unwrap());
     println!("writing generated html to {:?}", output_path);
-    std::fs::write(output_path, template)?;
+    std::fs::write(output_path, rendered)?;
 
     Ok(())
 }
@@ -0,0 +1,200 @@
+//! Persistence for historical per-deck snapshots, stored in a small
+//! writable SQLite database separate from the (read-only) Anki
+//! collection. Schema changes are expressed as an ordered list of
+//! migrations so the database can evolve across runs without losing
+//! history.
+
+use anyhow::Result;
+use rusqlite::{params, Connection};
+
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE snapshots (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        recorded_at TEXT NOT NULL,
+        deck_id INTEGER NOT NULL,
+        deck_name TEXT NOT NULL,
+        n_cards INTEGER NOT NULL,
+        n_learned INTEGER NOT NULL,
+        n_new INTEGER NOT NULL,
+        n_learning INTEGER NOT NULL,
+        n_review INTEGER NOT NULL
+    );",
+];
+
+/// A single deck's counts for one run, ready to be persisted.
+#[derive(Debug)]
+pub struct DeckSnapshot {
+    pub deck_id: i64,
+    pub deck_name: String,
+    pub n_cards: usize,
+    pub n_learned: usize,
+    pub n_new: usize,
+    pub n_learning: usize,
+    pub n_review: usize,
+}
+
+/// One point of the learned-over-time series for a set of decks.
+#[derive(Debug)]
+pub struct ProgressPoint {
+    pub recorded_at: String,
+    pub n_cards: usize,
+    pub n_learned: usize,
+}
+
+pub struct ProgressDb {
+    conn: Connection,
+}
+
+impl ProgressDb {
+    pub fn open(path: &std::path::Path) -> Result<Self> {
+        let mut conn = Connection::open(path)?;
+        Self::migrate(&mut conn)?;
+        Ok(ProgressDb { conn })
+    }
+
+    fn migrate(conn: &mut Connection) -> Result<()> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS schema_migrations (version INTEGER NOT NULL PRIMARY KEY);",
+        )?;
+        let applied: usize =
+            conn.query_row("SELECT COUNT(*) FROM schema_migrations", [], |row| row.get(0))?;
+
+        let tx = conn.transaction()?;
+        for (version, migration) in MIGRATIONS.iter().enumerate().skip(applied) {
+            tx.execute_batch(migration)?;
+            tx.execute(
+                "INSERT INTO schema_migrations (version) VALUES (?1)",
+                params![version as i64],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Records one row per deck for this run, all in a single transaction.
+    pub fn record_run(&mut self, recorded_at: &str, snapshots: &[DeckSnapshot]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        for snapshot in snapshots {
+            tx.execute(
+                "INSERT INTO snapshots \
+                    (recorded_at, deck_id, deck_name, n_cards, n_learned, n_new, n_learning, n_review) \
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    recorded_at,
+                    snapshot.deck_id,
+                    snapshot.deck_name,
+                    snapshot.n_cards as i64,
+                    snapshot.n_learned as i64,
+                    snapshot.n_new as i64,
+                    snapshot.n_learning as i64,
+                    snapshot.n_review as i64,
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Returns the aggregate learned-over-time series across the given
+    /// decks, one point per distinct `recorded_at` timestamp.
+    pub fn fetch_progress(&self, deck_ids: &[i64]) -> Result<Vec<ProgressPoint>> {
+        let placeholders = deck_ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+        let sql = format!(
+            "SELECT recorded_at, SUM(n_cards), SUM(n_learned) \
+                FROM snapshots \
+                WHERE deck_id IN ({placeholders}) \
+                GROUP BY recorded_at \
+                ORDER BY recorded_at",
+            placeholders = placeholders
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let points = stmt
+            .query_map(rusqlite::params_from_iter(deck_ids.iter()), |row| {
+                Ok(ProgressPoint {
+                    recorded_at: row.get(0)?,
+                    n_cards: row.get::<_, i64>(1)? as usize,
+                    n_learned: row.get::<_, i64>(2)? as usize,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn open_test_db() -> ProgressDb {
+        ProgressDb::open(Path::new(":memory:")).unwrap()
+    }
+
+    fn snapshot(deck_id: i64, n_cards: usize, n_learned: usize) -> DeckSnapshot {
+        DeckSnapshot {
+            deck_id,
+            deck_name: format!("deck-{deck_id}"),
+            n_cards,
+            n_learned,
+            n_new: n_cards - n_learned,
+            n_learning: 0,
+            n_review: n_learned,
+        }
+    }
+
+    #[test]
+    fn open_runs_migrations_idempotently() {
+        let path = std::env::temp_dir().join(format!(
+            "pipette-progress-test-{}-{}.sqlite3",
+            std::process::id(),
+            line!()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        ProgressDb::open(&path).unwrap();
+        // Re-opening an already-migrated database must not try to re-run
+        // (and fail on) migrations that were already applied.
+        ProgressDb::open(&path).unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn record_run_and_fetch_progress_aggregates_across_decks() {
+        let mut db = open_test_db();
+        db.record_run(
+            "2026-01-01T00:00:00Z",
+            &[snapshot(1, 10, 4), snapshot(2, 5, 1)],
+        )
+        .unwrap();
+        db.record_run(
+            "2026-01-02T00:00:00Z",
+            &[snapshot(1, 10, 6), snapshot(2, 5, 2)],
+        )
+        .unwrap();
+
+        let series = db.fetch_progress(&[1, 2]).unwrap();
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[0].recorded_at, "2026-01-01T00:00:00Z");
+        assert_eq!(series[0].n_cards, 15);
+        assert_eq!(series[0].n_learned, 5);
+        assert_eq!(series[1].recorded_at, "2026-01-02T00:00:00Z");
+        assert_eq!(series[1].n_cards, 15);
+        assert_eq!(series[1].n_learned, 8);
+    }
+
+    #[test]
+    fn fetch_progress_only_includes_requested_decks() {
+        let mut db = open_test_db();
+        db.record_run(
+            "2026-01-01T00:00:00Z",
+            &[snapshot(1, 10, 4), snapshot(2, 5, 1)],
+        )
+        .unwrap();
+
+        let series = db.fetch_progress(&[1]).unwrap();
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].n_cards, 10);
+        assert_eq!(series[0].n_learned, 4);
+    }
+}